@@ -0,0 +1,371 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+#[cfg(feature = "brotli")]
+use async_compression::stream::BrotliEncoder;
+#[cfg(feature = "gzip")]
+use async_compression::stream::GzipEncoder;
+#[cfg(feature = "zlib")]
+use async_compression::stream::ZlibEncoder;
+#[cfg(feature = "zstd")]
+use async_compression::stream::ZstdEncoder;
+use bytes::Bytes;
+use futures::stream::Stream;
+
+#[cfg(feature = "lz4")]
+use crate::compression::lz4::Lz4Encoder;
+use crate::{
+    compression::Compression,
+    error::{Error, Result},
+};
+
+/// Payloads that fit below this size are buffered and compressed as a single
+/// inline frame; larger payloads hand off to the streaming encoder so they
+/// don't buffer unbounded. Mirrors `MAX_CHUNK_SIZE_DECODE_IN_PLACE` on the
+/// decode side.
+pub(crate) const MAX_CHUNK_SIZE_ENCODE_IN_PLACE: usize = 1024;
+
+/// Wraps an outgoing insert payload, producing compressed frames and the
+/// matching `Content-Encoding` for the request.
+pub struct Encoder<S> {
+    compression: Compression,
+    state: State<S>,
+}
+
+enum State<S> {
+    /// Accumulating the payload until it ends or crosses the inline threshold.
+    Buffering { source: Option<S>, buf: Vec<u8> },
+    /// Streaming compressed frames, feeding the buffered prefix first.
+    Streaming(Coder<S>),
+    Done,
+}
+
+impl<S> Encoder<S>
+where
+    S: Stream<Item = Bytes> + Unpin,
+{
+    pub fn new(stream: S, compression: Compression) -> Self {
+        Self {
+            compression,
+            state: State::Buffering {
+                source: Some(stream),
+                buf: Vec::new(),
+            },
+        }
+    }
+
+    /// The `Content-Encoding` header value matching the active codec, or `None`
+    /// when the payload is sent uncompressed.
+    pub fn content_encoding(&self) -> Option<&'static str> {
+        match self.compression {
+            Compression::None => None,
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 => Some("lz4"),
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => Some("gzip"),
+            #[cfg(feature = "zlib")]
+            Compression::Zlib => Some("deflate"),
+            #[cfg(feature = "brotli")]
+            Compression::Brotli => Some("br"),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Some("zstd"),
+        }
+    }
+}
+
+impl<S> Stream for Encoder<S>
+where
+    S: Stream<Item = Bytes> + Unpin,
+{
+    type Item = Result<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let State::Streaming(coder) = &mut this.state {
+                let poll = Pin::new(coder).poll_next(cx);
+                if let Poll::Ready(None) = poll {
+                    this.state = State::Done;
+                }
+                return poll;
+            }
+
+            if let State::Done = this.state {
+                return Poll::Ready(None);
+            }
+
+            // Buffering: pull from the source until it ends or the buffer grows
+            // past the inline threshold.
+            let source_done = if let State::Buffering { source, buf } = &mut this.state {
+                let src = source.as_mut().expect("source present while buffering");
+                match Pin::new(src).poll_next(cx) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(Some(chunk)) => {
+                        buf.extend_from_slice(&chunk);
+                        if buf.len() < MAX_CHUNK_SIZE_ENCODE_IN_PLACE {
+                            continue;
+                        }
+                        false
+                    }
+                    Poll::Ready(None) => true,
+                }
+            } else {
+                unreachable!("Streaming and Done are handled above")
+            };
+
+            let (source, buf) = match std::mem::replace(&mut this.state, State::Done) {
+                State::Buffering { source, buf } => {
+                    (source.expect("source present while buffering"), buf)
+                }
+                _ => unreachable!("state was just matched as Buffering"),
+            };
+
+            if source_done && buf.len() < MAX_CHUNK_SIZE_ENCODE_IN_PLACE {
+                // The whole payload fit under the threshold and the source is
+                // already exhausted: compress it as one in-place frame instead
+                // of paying the streaming encoder's per-poll overhead. `source`
+                // is spent and dropped here rather than handed to a stream.
+                drop(source);
+                return Poll::Ready(Some(encode_in_place(this.compression, &buf)));
+            }
+
+            // Threshold crossed: hand the buffered prefix and the remaining
+            // source off to the streaming encoder.
+            let head = Bytes::from(buf);
+            let stream = Prefixed::new(head, source);
+            this.state = State::Streaming(Coder::new(stream, this.compression));
+        }
+    }
+}
+
+/// Synchronously compresses an already fully-buffered payload as a single
+/// frame. Used for payloads under `MAX_CHUNK_SIZE_ENCODE_IN_PLACE`, where
+/// spinning up the streaming (async-compression) pipeline would be pure
+/// overhead — and where driving that pipeline synchronously would otherwise
+/// require assuming it never returns `Pending`, which a library can't do.
+fn encode_in_place(compression: Compression, buf: &[u8]) -> Result<Bytes> {
+    use std::io::Write;
+
+    match compression {
+        Compression::None => Ok(Bytes::copy_from_slice(buf)),
+        #[cfg(feature = "lz4")]
+        Compression::Lz4 => Ok(Bytes::from(crate::compression::lz4::compress(buf))),
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => {
+            let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            enc.write_all(buf).map_err(Error::encode_io)?;
+            enc.finish().map(Bytes::from).map_err(Error::encode_io)
+        }
+        #[cfg(feature = "zlib")]
+        Compression::Zlib => {
+            let mut enc =
+                flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            enc.write_all(buf).map_err(Error::encode_io)?;
+            enc.finish().map(Bytes::from).map_err(Error::encode_io)
+        }
+        #[cfg(feature = "brotli")]
+        Compression::Brotli => {
+            let mut out = Vec::new();
+            {
+                let mut enc = brotli::CompressorWriter::new(&mut out, 4096, 11, 22);
+                enc.write_all(buf).map_err(Error::encode_io)?;
+            }
+            Ok(Bytes::from(out))
+        }
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => {
+            let mut enc = zstd::stream::write::Encoder::new(Vec::new(), 0).map_err(Error::encode_io)?;
+            enc.write_all(buf).map_err(Error::encode_io)?;
+            enc.finish().map(Bytes::from).map_err(Error::encode_io)
+        }
+    }
+}
+
+enum Coder<S> {
+    Plain(Prefixed<S>),
+    #[cfg(feature = "lz4")]
+    Lz4(Lz4Encoder<Prefixed<S>>),
+    #[cfg(feature = "gzip")]
+    Gzip(Box<GzipEncoder<PayloadAdapter<Prefixed<S>>>>),
+    #[cfg(feature = "zlib")]
+    Zlib(Box<ZlibEncoder<PayloadAdapter<Prefixed<S>>>>),
+    #[cfg(feature = "brotli")]
+    Brotli(Box<BrotliEncoder<PayloadAdapter<Prefixed<S>>>>),
+    #[cfg(feature = "zstd")]
+    Zstd(Box<ZstdEncoder<PayloadAdapter<Prefixed<S>>>>),
+}
+
+impl<S> Coder<S>
+where
+    S: Stream<Item = Bytes> + Unpin,
+{
+    fn new(stream: Prefixed<S>, compression: Compression) -> Self {
+        match compression {
+            Compression::None => Coder::Plain(stream),
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 => Coder::Lz4(Lz4Encoder::new(stream)),
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => Coder::Gzip(Box::new(GzipEncoder::new(PayloadAdapter(stream)))),
+            #[cfg(feature = "zlib")]
+            Compression::Zlib => Coder::Zlib(Box::new(ZlibEncoder::new(PayloadAdapter(stream)))),
+            #[cfg(feature = "brotli")]
+            Compression::Brotli => {
+                Coder::Brotli(Box::new(BrotliEncoder::new(PayloadAdapter(stream))))
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Coder::Zstd(Box::new(ZstdEncoder::new(PayloadAdapter(stream)))),
+        }
+    }
+}
+
+impl<S> Stream for Coder<S>
+where
+    S: Stream<Item = Bytes> + Unpin,
+{
+    type Item = Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match *self {
+            Coder::Plain(ref mut inner) => Pin::new(inner).poll_next(cx).map(|opt| opt.map(Ok)),
+            #[cfg(feature = "lz4")]
+            Coder::Lz4(ref mut inner) => Pin::new(inner).poll_next(cx),
+            #[cfg(feature = "gzip")]
+            Coder::Gzip(ref mut inner) => map_encode_poll(Pin::new(inner).poll_next(cx)),
+            #[cfg(feature = "zlib")]
+            Coder::Zlib(ref mut inner) => map_encode_poll(Pin::new(inner).poll_next(cx)),
+            #[cfg(feature = "brotli")]
+            Coder::Brotli(ref mut inner) => map_encode_poll(Pin::new(inner).poll_next(cx)),
+            #[cfg(feature = "zstd")]
+            Coder::Zstd(ref mut inner) => map_encode_poll(Pin::new(inner).poll_next(cx)),
+        }
+    }
+}
+
+/// Emits a buffered prefix before delegating to the remaining source stream.
+struct Prefixed<S> {
+    head: Option<Bytes>,
+    tail: S,
+    /// Set once `tail` has yielded `None`, so we never poll it again. `Stream`
+    /// doesn't guarantee `FusedStream`, and `tail` is the caller's own source.
+    tail_done: bool,
+}
+
+impl<S> Prefixed<S> {
+    fn new(head: Bytes, tail: S) -> Self {
+        Self {
+            head: Some(head),
+            tail,
+            tail_done: false,
+        }
+    }
+}
+
+impl<S> Stream for Prefixed<S>
+where
+    S: Stream<Item = Bytes> + Unpin,
+{
+    type Item = Bytes;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(head) = self.head.take() {
+            if !head.is_empty() {
+                return Poll::Ready(Some(head));
+            }
+        }
+        if self.tail_done {
+            return Poll::Ready(None);
+        }
+        let poll = Pin::new(&mut self.tail).poll_next(cx);
+        if let Poll::Ready(None) = poll {
+            self.tail_done = true;
+        }
+        poll
+    }
+}
+
+#[cfg(any(feature = "gzip", feature = "zlib", feature = "brotli", feature = "zstd"))]
+struct PayloadAdapter<S>(S);
+
+#[cfg(any(feature = "gzip", feature = "zlib", feature = "brotli", feature = "zstd"))]
+impl<S> Stream for PayloadAdapter<S>
+where
+    S: Stream<Item = Bytes> + Unpin,
+{
+    type Item = std::io::Result<bytes_05::Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.0)
+            .poll_next(cx)
+            .map(|opt| opt.map(|bytes| Ok(to_bytes05(bytes))))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+#[cfg(feature = "bytes-05")]
+fn to_bytes05(bytes: Bytes) -> bytes_05::Bytes {
+    bytes.to_vec().into()
+}
+
+#[cfg(feature = "bytes-05")]
+fn from_bytes05(bytes: bytes_05::Bytes) -> Bytes {
+    bytes.to_vec().into()
+}
+
+#[cfg(any(feature = "gzip", feature = "zlib", feature = "brotli", feature = "zstd"))]
+fn map_encode_poll(
+    poll: Poll<Option<std::io::Result<bytes_05::Bytes>>>,
+) -> Poll<Option<Result<Bytes>>> {
+    match poll {
+        Poll::Ready(Some(Ok(val))) => Poll::Ready(Some(Ok(from_bytes05(val)))),
+        Poll::Ready(Some(Err(err))) => Poll::Ready(Some(Err(Error::encode_io(err)))),
+        Poll::Ready(None) => Poll::Ready(None),
+        Poll::Pending => Poll::Pending,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{stream, task::noop_waker_ref};
+
+    /// Drives a fully in-memory `Encoder` to completion, returning each frame
+    /// it emitted. Panics on `Pending` since nothing here ever waits on I/O.
+    fn drain(mut encoder: Pin<&mut Encoder<impl Stream<Item = Bytes> + Unpin>>) -> Vec<Bytes> {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut frames = Vec::new();
+        loop {
+            match encoder.as_mut().poll_next(&mut cx) {
+                Poll::Ready(Some(Ok(bytes))) => frames.push(bytes),
+                Poll::Ready(Some(Err(err))) => panic!("encode failed: {err}"),
+                Poll::Ready(None) => return frames,
+                Poll::Pending => panic!("encoder unexpectedly pending over an in-memory source"),
+            }
+        }
+    }
+
+    #[test]
+    fn payload_under_threshold_encodes_as_a_single_in_place_frame() {
+        let body = stream::iter(vec![Bytes::from_static(b"hello world")]);
+        let mut encoder = Box::pin(Encoder::new(body, Compression::None));
+        let frames = drain(encoder.as_mut());
+        assert_eq!(frames, vec![Bytes::from_static(b"hello world")]);
+    }
+
+    #[test]
+    fn payload_at_or_above_threshold_streams_multiple_frames() {
+        let chunk = Bytes::from(vec![0u8; MAX_CHUNK_SIZE_ENCODE_IN_PLACE]);
+        let body = stream::iter(vec![chunk.clone(), chunk]);
+        let mut encoder = Box::pin(Encoder::new(body, Compression::None));
+        let frames = drain(encoder.as_mut());
+        assert!(
+            frames.len() >= 2,
+            "expected the streaming path to emit more than one frame, got {}",
+            frames.len()
+        );
+    }
+}
@@ -0,0 +1,68 @@
+use std::{fmt, io};
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Errors surfaced by the HTTP transport and the compression pipeline.
+#[derive(Debug)]
+pub enum Error {
+    /// The server replied with a non-`200` status; carries the response body.
+    BadResponse(String),
+    /// A failure reading or writing the underlying HTTP stream.
+    Network(hyper::Error),
+    /// A failure while decompressing a response body.
+    Decode(io::Error),
+    /// A failure while compressing an outbound request body.
+    Encode(io::Error),
+    /// The `X-ClickHouse-Summary` trailer/header was present but malformed.
+    Summary(serde_json::Error),
+}
+
+impl Error {
+    /// Wraps an IO error raised on the response decompression path.
+    pub fn decode_io(err: io::Error) -> Self {
+        Self::Decode(err)
+    }
+
+    /// Wraps an IO error raised on the request compression path.
+    pub fn encode_io(err: io::Error) -> Self {
+        Self::Encode(err)
+    }
+
+    /// Flattens the error back into an `io::Error`, reusing the inner error for
+    /// the codec arms so the original kind/message is preserved.
+    pub fn into_io(self) -> io::Error {
+        match self {
+            Self::Decode(err) | Self::Encode(err) => err,
+            other => io::Error::new(io::ErrorKind::Other, other),
+        }
+    }
+}
+
+impl From<hyper::Error> for Error {
+    fn from(err: hyper::Error) -> Self {
+        Self::Network(err)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadResponse(reason) => write!(f, "bad response: {reason}"),
+            Self::Network(err) => write!(f, "network error: {err}"),
+            Self::Decode(err) => write!(f, "decode error: {err}"),
+            Self::Encode(err) => write!(f, "encode error: {err}"),
+            Self::Summary(err) => write!(f, "invalid summary: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::BadResponse(_) => None,
+            Self::Network(err) => Some(err),
+            Self::Decode(err) | Self::Encode(err) => Some(err),
+            Self::Summary(err) => Some(err),
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use std::{
+    future::Future,
     pin::Pin,
+    sync::{Arc, Mutex},
     task::{Context, Poll},
 };
 
@@ -9,9 +11,16 @@ use async_compression::stream::BrotliDecoder;
 use async_compression::stream::GzipDecoder;
 #[cfg(feature = "zlib")]
 use async_compression::stream::ZlibDecoder;
+#[cfg(feature = "zstd")]
+use async_compression::stream::ZstdDecoder;
 use bytes::Bytes;
 use futures::stream::Stream;
-use hyper::{body, client::ResponseFuture, Body, StatusCode};
+use hyper::{
+    body,
+    client::ResponseFuture,
+    header::{ACCEPT_ENCODING, CONTENT_ENCODING},
+    Body, StatusCode,
+};
 
 #[cfg(feature = "lz4")]
 use crate::compression::lz4::Lz4Decoder;
@@ -21,17 +30,27 @@ use crate::{
 };
 
 pub enum Response {
-    Waiting(ResponseFuture, Compression),
+    Waiting(ResponseFuture, Compression, bool),
     Loading(Chunks),
 }
 
 impl Response {
     pub fn new(future: ResponseFuture, compression: Compression) -> Self {
-        Self::Waiting(future, compression)
+        Self::Waiting(future, compression, false)
+    }
+
+    /// Offloads large decode frames to a blocking thread pool so they don't
+    /// stall the async reactor. Small frames still decode inline.
+    pub fn with_blocking_decode(mut self, blocking: bool) -> Self {
+        if let Self::Waiting(_, _, b) = &mut self {
+            *b = blocking;
+        }
+        self
     }
 
     pub async fn resolve(&mut self) -> Result<&mut Chunks> {
-        if let Self::Waiting(response, compression) = self {
+        if let Self::Waiting(response, compression, blocking) = self {
+            let blocking = *blocking;
             let response = response.await?;
 
             if response.status() != StatusCode::OK {
@@ -41,21 +60,48 @@ impl Response {
                 return Err(Error::BadResponse(reason));
             }
 
-            let body = response.into_body();
-            let chunks = match compression {
+            // Trust the server's actual Content-Encoding over what we asked for.
+            let resolved = from_content_encoding(response.headers(), *compression);
+
+            // Fallback source for X-ClickHouse-Summary if sent as a header.
+            let headers = response.headers().clone();
+
+            let trailers: TrailerSlot = Arc::new(Mutex::new(None));
+            let body = TrailerBody::new(response.into_body(), trailers.clone());
+            let chunks = match resolved {
                 Compression::None => Inner::Plain(body),
                 #[cfg(feature = "lz4")]
                 Compression::Lz4 => Inner::Lz4(Lz4Decoder::new(body)),
                 #[cfg(feature = "gzip")]
-                Compression::Gzip => Inner::Gzip(Box::new(GzipDecoder::new(BodyAdapter(body)))),
+                Compression::Gzip => Inner::Gzip(Box::new(if blocking {
+                    Offload::blocking(BodyAdapter(body), SyncDecoder::gzip())
+                } else {
+                    Offload::inline(GzipDecoder::new(BodyAdapter(body)))
+                })),
                 #[cfg(feature = "zlib")]
-                Compression::Zlib => Inner::Zlib(Box::new(ZlibDecoder::new(BodyAdapter(body)))),
+                Compression::Zlib => Inner::Zlib(Box::new(if blocking {
+                    Offload::blocking(BodyAdapter(body), SyncDecoder::zlib())
+                } else {
+                    Offload::inline(ZlibDecoder::new(BodyAdapter(body)))
+                })),
                 #[cfg(feature = "brotli")]
-                Compression::Brotli => {
-                    Inner::Brotli(Box::new(BrotliDecoder::new(BodyAdapter(body))))
-                }
+                Compression::Brotli => Inner::Brotli(Box::new(if blocking {
+                    Offload::blocking(BodyAdapter(body), SyncDecoder::brotli())
+                } else {
+                    Offload::inline(BrotliDecoder::new(BodyAdapter(body)))
+                })),
+                #[cfg(feature = "zstd")]
+                Compression::Zstd => Inner::Zstd(Box::new(if blocking {
+                    Offload::blocking(BodyAdapter(body), SyncDecoder::zstd())
+                } else {
+                    Offload::inline(ZstdDecoder::new(BodyAdapter(body)))
+                })),
             };
-            *self = Self::Loading(Chunks(chunks));
+            *self = Self::Loading(Chunks {
+                inner: chunks,
+                headers,
+                trailers,
+            });
         }
 
         match self {
@@ -63,20 +109,213 @@ impl Response {
             Self::Loading(chunks) => Ok(chunks),
         }
     }
+
+    /// Returns ClickHouse's per-query statistics once the body has been fully
+    /// read, parsed from the `X-ClickHouse-Summary` trailer (or header).
+    /// `Ok(None)` means no summary was sent; `Err` means one was sent but
+    /// couldn't be parsed.
+    pub fn summary(&self) -> Result<Option<Summary>> {
+        match self {
+            Self::Loading(chunks) => chunks.summary(),
+            Self::Waiting(..) => Ok(None),
+        }
+    }
+}
+
+/// Per-query read/write statistics reported by ClickHouse in the
+/// `X-ClickHouse-Summary` trailer. All counters are cumulative for the query.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct Summary {
+    #[serde(default, deserialize_with = "number_from_str")]
+    pub read_rows: u64,
+    #[serde(default, deserialize_with = "number_from_str")]
+    pub read_bytes: u64,
+    #[serde(default, deserialize_with = "number_from_str")]
+    pub written_rows: u64,
+    #[serde(default, deserialize_with = "number_from_str")]
+    pub written_bytes: u64,
+    #[serde(default, deserialize_with = "number_from_str")]
+    pub total_rows_to_read: u64,
+    #[serde(default, deserialize_with = "number_from_str")]
+    pub result_rows: u64,
+    #[serde(default, deserialize_with = "number_from_str")]
+    pub result_bytes: u64,
+    #[serde(default, deserialize_with = "number_from_str")]
+    pub elapsed_ns: u64,
+}
+
+// ClickHouse encodes every summary counter as a JSON string, so parse them back
+// into integers on the way in.
+fn number_from_str<'de, D>(deserializer: D) -> std::result::Result<u64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::Deserialize;
+    let raw = String::deserialize(deserializer)?;
+    raw.parse().map_err(serde::de::Error::custom)
+}
+
+/// Picks the decoder from the response's `Content-Encoding`, falling back to
+/// `configured` when the header is missing or carries an unknown codec.
+///
+/// `Content-Encoding` may list several codings in application order (e.g.
+/// `gzip, br`); the last one is the outermost and the one we need to peel
+/// off first, so match on that, case-insensitively.
+fn from_content_encoding(headers: &hyper::HeaderMap, configured: Compression) -> Compression {
+    let encoding = match headers.get(CONTENT_ENCODING).and_then(|v| v.to_str().ok()) {
+        Some(encoding) => encoding,
+        None => return configured,
+    };
+
+    let outermost = match encoding.split(',').next_back() {
+        Some(token) if !token.trim().is_empty() => token.trim().to_ascii_lowercase(),
+        _ => return configured,
+    };
+
+    match outermost.as_str() {
+        #[cfg(feature = "lz4")]
+        "lz4" => Compression::Lz4,
+        #[cfg(feature = "gzip")]
+        "gzip" => Compression::Gzip,
+        #[cfg(feature = "zlib")]
+        "deflate" => Compression::Zlib,
+        #[cfg(feature = "brotli")]
+        "br" => Compression::Brotli,
+        #[cfg(feature = "zstd")]
+        "zstd" => Compression::Zstd,
+        "identity" => Compression::None,
+        _ => configured,
+    }
+}
+
+/// Builds the `Accept-Encoding` request header from the codecs compiled into
+/// this build, keeping request and response negotiation symmetric.
+pub fn accept_encoding() -> String {
+    let mut codecs = vec!["identity"];
+    if cfg!(feature = "gzip") {
+        codecs.push("gzip");
+    }
+    if cfg!(feature = "zlib") {
+        codecs.push("deflate");
+    }
+    if cfg!(feature = "brotli") {
+        codecs.push("br");
+    }
+    if cfg!(feature = "zstd") {
+        codecs.push("zstd");
+    }
+    if cfg!(feature = "lz4") {
+        codecs.push("lz4");
+    }
+    codecs.join(",")
+}
+
+/// Sets `Accept-Encoding` on an outgoing request to the codecs this build
+/// supports, so the server may compress the response. Call this while building
+/// every request that reads a body back.
+pub fn with_accept_encoding(
+    builder: hyper::http::request::Builder,
+) -> hyper::http::request::Builder {
+    builder.header(ACCEPT_ENCODING, accept_encoding())
+}
+
+/// Shared slot for the trailing `HeaderMap` captured once the underlying
+/// `hyper::Body` reaches end-of-stream, whatever decoder sits on top of it.
+type TrailerSlot = Arc<Mutex<Option<hyper::HeaderMap>>>;
+
+/// Transparent wrapper over a `hyper::Body` that records its trailing headers
+/// into a shared slot once the data stream is exhausted. Placed directly on top
+/// of the body so trailers stay reachable through any decoder variant.
+struct TrailerBody {
+    body: Body,
+    trailers: TrailerSlot,
+    /// Set once the data stream itself has yielded `None`; from then on we only
+    /// poll `poll_trailers` until it resolves.
+    data_done: bool,
+}
+
+impl TrailerBody {
+    fn new(body: Body, trailers: TrailerSlot) -> Self {
+        Self {
+            body,
+            trailers,
+            data_done: false,
+        }
+    }
 }
 
-pub struct Chunks(Inner);
+impl Stream for TrailerBody {
+    type Item = std::result::Result<Bytes, hyper::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if !self.data_done {
+            match Pin::new(&mut self.body).poll_next(cx) {
+                Poll::Ready(None) => self.data_done = true,
+                other => return other,
+            }
+        }
+
+        // The trailer frame often isn't off the socket yet here, so propagate
+        // `Pending` rather than giving up after one poll.
+        match hyper::body::HttpBody::poll_trailers(Pin::new(&mut self.body), cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Ok(Some(trailers))) => {
+                if let Ok(mut slot) = self.trailers.lock() {
+                    *slot = Some(trailers);
+                }
+                Poll::Ready(None)
+            }
+            Poll::Ready(Ok(None)) | Poll::Ready(Err(_)) => Poll::Ready(None),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.body.size_hint()
+    }
+}
+
+pub struct Chunks {
+    inner: Inner,
+    headers: hyper::HeaderMap,
+    trailers: TrailerSlot,
+}
+
+impl Chunks {
+    const SUMMARY_HEADER: &'static str = "x-clickhouse-summary";
+
+    fn summary(&self) -> Result<Option<Summary>> {
+        // Prefer the trailer (sent after the body) and fall back to the header.
+        let from_trailer = self
+            .trailers
+            .lock()
+            .ok()
+            .and_then(|t| t.as_ref().and_then(|m| m.get(Self::SUMMARY_HEADER).cloned()));
+        let value = match from_trailer.or_else(|| self.headers.get(Self::SUMMARY_HEADER).cloned())
+        {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+
+        serde_json::from_slice(value.as_bytes())
+            .map(Some)
+            .map_err(Error::Summary)
+    }
+}
 
 enum Inner {
-    Plain(Body),
+    Plain(TrailerBody),
     #[cfg(feature = "lz4")]
-    Lz4(Lz4Decoder<Body>),
+    Lz4(Lz4Decoder<TrailerBody>),
+    // Boxed so a decoder doesn't inflate the size of every `Inner`/`Chunks` by
+    // the size of the largest codec's state, same as `encoding::Coder` does.
     #[cfg(feature = "gzip")]
-    Gzip(Box<GzipDecoder<BodyAdapter>>),
+    Gzip(Box<Offload<GzipDecoder<BodyAdapter>>>),
     #[cfg(feature = "zlib")]
-    Zlib(Box<ZlibDecoder<BodyAdapter>>),
+    Zlib(Box<Offload<ZlibDecoder<BodyAdapter>>>),
     #[cfg(feature = "brotli")]
-    Brotli(Box<BrotliDecoder<BodyAdapter>>),
+    Brotli(Box<Offload<BrotliDecoder<BodyAdapter>>>),
+    #[cfg(feature = "zstd")]
+    Zstd(Box<Offload<ZstdDecoder<BodyAdapter>>>),
     Empty,
 }
 
@@ -85,21 +324,25 @@ impl Stream for Chunks {
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         use Inner::*;
-        let res = match self.0 {
+        let res = match self.inner {
             Plain(ref mut inner) => map_poll_err(Pin::new(inner).poll_next(cx), Into::into),
             #[cfg(feature = "lz4")]
             Lz4(ref mut inner) => Pin::new(inner).poll_next(cx),
             #[cfg(feature = "gzip")]
-            Gzip(ref mut inner) => map_compression_poll(Pin::new(inner).poll_next(cx)),
+            Gzip(ref mut inner) => map_compression_poll(Pin::new(inner.as_mut()).poll_next(cx)),
             #[cfg(feature = "zlib")]
-            Zlib(ref mut inner) => map_compression_poll(Pin::new(inner).poll_next(cx)),
+            Zlib(ref mut inner) => map_compression_poll(Pin::new(inner.as_mut()).poll_next(cx)),
             #[cfg(feature = "brotli")]
-            Brotli(ref mut inner) => map_compression_poll(Pin::new(inner).poll_next(cx)),
+            Brotli(ref mut inner) => map_compression_poll(Pin::new(inner.as_mut()).poll_next(cx)),
+            #[cfg(feature = "zstd")]
+            Zstd(ref mut inner) => map_compression_poll(Pin::new(inner.as_mut()).poll_next(cx)),
             Empty => Poll::Ready(None),
         };
 
         if let Poll::Ready(None) = res {
-            self.0 = Inner::Empty;
+            // Trailers were already captured into the shared slot by `TrailerBody`
+            // when the underlying body ended; just release the decoder state.
+            self.inner = Inner::Empty;
         }
 
         res
@@ -107,7 +350,7 @@ impl Stream for Chunks {
 
     fn size_hint(&self) -> (usize, Option<usize>) {
         use Inner::*;
-        match &self.0 {
+        match &self.inner {
             Plain(inner) => inner.size_hint(),
             #[cfg(feature = "lz4")]
             Lz4(inner) => inner.size_hint(),
@@ -117,15 +360,264 @@ impl Stream for Chunks {
             Zlib(inner) => inner.size_hint(),
             #[cfg(feature = "brotli")]
             Brotli(inner) => inner.size_hint(),
+            #[cfg(feature = "zstd")]
+            Zstd(inner) => inner.size_hint(),
             Empty => (0, Some(0)),
         }
     }
 }
 
-#[cfg(any(feature = "gzip", feature = "zlib", feature = "brotli"))]
-struct BodyAdapter(Body);
+/// Compressed blocks at or above this size are decoded on the blocking pool;
+/// smaller frames stay inline to avoid the task-spawn overhead.
+#[cfg(any(feature = "gzip", feature = "zlib", feature = "brotli", feature = "zstd"))]
+const MAX_CHUNK_SIZE_DECODE_IN_PLACE: usize = 32 * 1024;
+
+#[cfg(any(feature = "gzip", feature = "zlib", feature = "brotli", feature = "zstd"))]
+type DecodedItem = std::io::Result<bytes_05::Bytes>;
+
+/// Decodes inline on the async executor (`Inline`, the default), or offloads
+/// decompression of large already-buffered frames to `spawn_blocking`
+/// (`Blocking`).
+/// `Blocking` runs its own sync codec stack (`SyncDecoder`, below) rather than
+/// reusing the `Inline` decoders, duplicating `flate2`/`brotli`/`zstd`.
+#[cfg(any(feature = "gzip", feature = "zlib", feature = "brotli", feature = "zstd"))]
+enum Offload<D> {
+    Inline(D),
+    Blocking(Blocking),
+}
+
+#[cfg(any(feature = "gzip", feature = "zlib", feature = "brotli", feature = "zstd"))]
+impl<D> Offload<D> {
+    fn inline(decoder: D) -> Self {
+        Self::Inline(decoder)
+    }
 
-#[cfg(any(feature = "gzip", feature = "zlib", feature = "brotli"))]
+    fn blocking(source: BodyAdapter, decoder: SyncDecoder) -> Self {
+        Self::Blocking(Blocking {
+            source,
+            decoder: Some(decoder),
+            in_flight: None,
+            finished: false,
+        })
+    }
+}
+
+#[cfg(any(feature = "gzip", feature = "zlib", feature = "brotli", feature = "zstd"))]
+impl<D> Stream for Offload<D>
+where
+    D: Stream<Item = DecodedItem> + Unpin,
+{
+    type Item = DecodedItem;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.get_mut() {
+            Offload::Inline(decoder) => Pin::new(decoder).poll_next(cx),
+            Offload::Blocking(blocking) => Pin::new(blocking).poll_next(cx),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            Offload::Inline(decoder) => decoder.size_hint(),
+            Offload::Blocking(_) => (0, None),
+        }
+    }
+}
+
+/// Reads compressed frames on the async executor and runs the decompression of
+/// large frames on the blocking pool, passing only already-buffered bytes so no
+/// network wait ever happens on a pool thread.
+#[cfg(any(feature = "gzip", feature = "zlib", feature = "brotli", feature = "zstd"))]
+struct Blocking {
+    source: BodyAdapter,
+    decoder: Option<SyncDecoder>,
+    in_flight: Option<tokio::task::JoinHandle<(SyncDecoder, std::io::Result<Vec<u8>>)>>,
+    finished: bool,
+}
+
+#[cfg(any(feature = "gzip", feature = "zlib", feature = "brotli", feature = "zstd"))]
+impl Stream for Blocking {
+    type Item = DecodedItem;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            // 1. Finish an in-flight decompress before pulling more input.
+            if let Some(handle) = this.in_flight.as_mut() {
+                match Pin::new(handle).poll(cx) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(Ok((decoder, res))) => {
+                        this.decoder = Some(decoder);
+                        this.in_flight = None;
+                        match res {
+                            Ok(out) if !out.is_empty() => return Poll::Ready(Some(Ok(out.into()))),
+                            Ok(_) => continue,
+                            Err(err) => return Poll::Ready(Some(Err(err))),
+                        }
+                    }
+                    Poll::Ready(Err(err)) => {
+                        // The task (and the decoder moved into it) is gone, so
+                        // there's nothing left to resume with; end the stream
+                        // rather than leaving `this.decoder` as `None` for the
+                        // next poll to unwrap.
+                        this.in_flight = None;
+                        this.finished = true;
+                        return Poll::Ready(Some(Err(join_err(err))));
+                    }
+                }
+            }
+
+            if this.finished {
+                return Poll::Ready(None);
+            }
+
+            // 2. Read the next compressed frame on the async executor.
+            match Pin::new(&mut this.source).poll_next(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(None) => {
+                    this.finished = true;
+                    let decoder = this.decoder.take().expect("blocking decoder is gone");
+                    match decoder.finish() {
+                        Ok(out) if !out.is_empty() => return Poll::Ready(Some(Ok(out.into()))),
+                        Ok(_) => return Poll::Ready(None),
+                        Err(err) => return Poll::Ready(Some(Err(err))),
+                    }
+                }
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                Poll::Ready(Some(Ok(frame))) => {
+                    let decoder = this.decoder.take().expect("blocking decoder is gone");
+
+                    // Small frames decode inline to avoid the task-spawn overhead;
+                    // large ones go to the pool. Gauged on the actual compressed
+                    // frame size, not `Stream::size_hint`.
+                    if frame.len() < MAX_CHUNK_SIZE_DECODE_IN_PLACE {
+                        let (decoder, res) = decode_frame(decoder, frame);
+                        this.decoder = Some(decoder);
+                        match res {
+                            Ok(out) if !out.is_empty() => return Poll::Ready(Some(Ok(out.into()))),
+                            Ok(_) => continue,
+                            Err(err) => return Poll::Ready(Some(Err(err))),
+                        }
+                    }
+
+                    this.in_flight =
+                        Some(tokio::task::spawn_blocking(move || decode_frame(decoder, frame)));
+                    continue;
+                }
+            }
+        }
+    }
+}
+
+/// Feeds one already-buffered compressed frame through the sync decoder and
+/// returns the decoder alongside whatever decompressed bytes it produced.
+#[cfg(any(feature = "gzip", feature = "zlib", feature = "brotli", feature = "zstd"))]
+fn decode_frame(
+    mut decoder: SyncDecoder,
+    frame: bytes_05::Bytes,
+) -> (SyncDecoder, std::io::Result<Vec<u8>>) {
+    let res = decoder.push(frame.as_ref());
+    (decoder, res)
+}
+
+/// Synchronous, persistent streaming decompressor used by `Blocking`. Each
+/// codec accumulates decompressed output into an inner `Vec` that we drain per
+/// frame, so cross-frame decoder state is preserved.
+#[cfg(any(feature = "gzip", feature = "zlib", feature = "brotli", feature = "zstd"))]
+enum SyncDecoder {
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::write::GzDecoder<Vec<u8>>),
+    #[cfg(feature = "zlib")]
+    Zlib(flate2::write::ZlibDecoder<Vec<u8>>),
+    #[cfg(feature = "brotli")]
+    Brotli(Box<brotli::DecompressorWriter<Vec<u8>>>),
+    #[cfg(feature = "zstd")]
+    Zstd(Box<zstd::stream::write::Decoder<'static, Vec<u8>>>),
+}
+
+#[cfg(any(feature = "gzip", feature = "zlib", feature = "brotli", feature = "zstd"))]
+impl SyncDecoder {
+    #[cfg(feature = "gzip")]
+    fn gzip() -> Self {
+        Self::Gzip(flate2::write::GzDecoder::new(Vec::new()))
+    }
+
+    #[cfg(feature = "zlib")]
+    fn zlib() -> Self {
+        Self::Zlib(flate2::write::ZlibDecoder::new(Vec::new()))
+    }
+
+    #[cfg(feature = "brotli")]
+    fn brotli() -> Self {
+        Self::Brotli(Box::new(brotli::DecompressorWriter::new(Vec::new(), 4096)))
+    }
+
+    #[cfg(feature = "zstd")]
+    fn zstd() -> Self {
+        Self::Zstd(Box::new(
+            zstd::stream::write::Decoder::new(Vec::new()).expect("zstd decoder init"),
+        ))
+    }
+
+    /// Writes one compressed frame and drains the decompressed output so far.
+    fn push(&mut self, input: &[u8]) -> std::io::Result<Vec<u8>> {
+        use std::io::Write;
+        match self {
+            #[cfg(feature = "gzip")]
+            Self::Gzip(dec) => {
+                dec.write_all(input)?;
+                dec.flush()?;
+                Ok(std::mem::take(dec.get_mut()))
+            }
+            #[cfg(feature = "zlib")]
+            Self::Zlib(dec) => {
+                dec.write_all(input)?;
+                dec.flush()?;
+                Ok(std::mem::take(dec.get_mut()))
+            }
+            #[cfg(feature = "brotli")]
+            Self::Brotli(dec) => {
+                dec.write_all(input)?;
+                dec.flush()?;
+                Ok(std::mem::take(dec.get_mut()))
+            }
+            #[cfg(feature = "zstd")]
+            Self::Zstd(dec) => {
+                dec.write_all(input)?;
+                dec.flush()?;
+                Ok(std::mem::take(dec.get_mut()))
+            }
+        }
+    }
+
+    /// Flushes any trailing decompressed output at end-of-stream.
+    fn finish(self) -> std::io::Result<Vec<u8>> {
+        match self {
+            #[cfg(feature = "gzip")]
+            Self::Gzip(dec) => dec.finish(),
+            #[cfg(feature = "zlib")]
+            Self::Zlib(dec) => dec.finish(),
+            #[cfg(feature = "brotli")]
+            Self::Brotli(mut dec) => {
+                use std::io::Write;
+                dec.flush()?;
+                Ok(std::mem::take(dec.get_mut()))
+            }
+            #[cfg(feature = "zstd")]
+            Self::Zstd(dec) => dec.finish(),
+        }
+    }
+}
+
+#[cfg(any(feature = "gzip", feature = "zlib", feature = "brotli", feature = "zstd"))]
+fn join_err(err: tokio::task::JoinError) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::Other, err)
+}
+
+#[cfg(any(feature = "gzip", feature = "zlib", feature = "brotli", feature = "zstd"))]
+struct BodyAdapter(TrailerBody);
+
+#[cfg(any(feature = "gzip", feature = "zlib", feature = "brotli", feature = "zstd"))]
 impl Stream for BodyAdapter {
     type Item = std::io::Result<bytes_05::Bytes>;
 
@@ -166,7 +658,7 @@ fn map_poll_err<T, E, E2>(
     }
 }
 
-#[cfg(any(feature = "gzip", feature = "zlib", feature = "brotli"))]
+#[cfg(any(feature = "gzip", feature = "zlib", feature = "brotli", feature = "zstd"))]
 fn map_compression_poll(
     poll: Poll<Option<std::io::Result<bytes_05::Bytes>>>,
 ) -> Poll<Option<Result<Bytes>>> {
@@ -177,3 +669,107 @@ fn map_compression_poll(
         Poll::Pending => Poll::Pending,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&str, &str)]) -> hyper::HeaderMap {
+        let mut map = hyper::HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(
+                hyper::header::HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                value.parse().unwrap(),
+            );
+        }
+        map
+    }
+
+    #[test]
+    fn content_encoding_absent_keeps_configured() {
+        let resolved = from_content_encoding(&headers(&[]), Compression::None);
+        assert_eq!(resolved, Compression::None);
+    }
+
+    #[test]
+    fn content_encoding_identity_disables_compression() {
+        let resolved =
+            from_content_encoding(&headers(&[("content-encoding", "identity")]), Compression::None);
+        assert_eq!(resolved, Compression::None);
+    }
+
+    #[test]
+    fn content_encoding_unknown_falls_back() {
+        let resolved = from_content_encoding(
+            &headers(&[("content-encoding", "snappy")]),
+            Compression::None,
+        );
+        assert_eq!(resolved, Compression::None);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn content_encoding_reads_header() {
+        let resolved =
+            from_content_encoding(&headers(&[("content-encoding", "gzip")]), Compression::None);
+        assert_eq!(resolved, Compression::Gzip);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn content_encoding_is_case_insensitive() {
+        let resolved =
+            from_content_encoding(&headers(&[("content-encoding", "GZIP")]), Compression::None);
+        assert_eq!(resolved, Compression::Gzip);
+    }
+
+    #[cfg(feature = "brotli")]
+    #[test]
+    fn content_encoding_token_list_uses_the_outermost_coding() {
+        let resolved = from_content_encoding(
+            &headers(&[("content-encoding", "gzip, br")]),
+            Compression::None,
+        );
+        assert_eq!(resolved, Compression::Brotli);
+    }
+
+    #[test]
+    fn accept_encoding_always_offers_identity() {
+        assert!(accept_encoding().split(',').any(|c| c == "identity"));
+    }
+
+    #[test]
+    fn summary_parses_string_encoded_counters() {
+        // ClickHouse sends every counter as a JSON string.
+        let raw = br#"{"read_rows":"12","read_bytes":"2048","written_rows":"0","elapsed_ns":"999"}"#;
+        let summary: Summary = serde_json::from_slice(raw).unwrap();
+        assert_eq!(summary.read_rows, 12);
+        assert_eq!(summary.read_bytes, 2048);
+        assert_eq!(summary.written_rows, 0);
+        assert_eq!(summary.elapsed_ns, 999);
+        // Counters absent from the payload default to zero.
+        assert_eq!(summary.result_rows, 0);
+    }
+
+    fn chunks_with_summary_header(value: Option<&str>) -> Chunks {
+        Chunks {
+            inner: Inner::Empty,
+            headers: match value {
+                Some(value) => headers(&[(Chunks::SUMMARY_HEADER, value)]),
+                None => headers(&[]),
+            },
+            trailers: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    #[test]
+    fn summary_is_none_without_a_summary_header() {
+        assert!(matches!(chunks_with_summary_header(None).summary(), Ok(None)));
+    }
+
+    #[test]
+    fn summary_surfaces_malformed_payload_as_an_error() {
+        let chunks = chunks_with_summary_header(Some("not json"));
+        assert!(matches!(chunks.summary(), Err(Error::Summary(_))));
+    }
+}